@@ -0,0 +1,43 @@
+// surfman/surfman/src/context.rs
+//
+//! Declarations that are common to all OpenGL contexts, regardless of platform.
+
+use crate::GLVersion;
+
+use bitflags::bitflags;
+use lazy_static::lazy_static;
+use std::sync::Mutex;
+
+lazy_static! {
+    // Ensures that only one context is created at a time, as some platform APIs are not reentrant.
+    pub(crate) static ref CREATE_CONTEXT_MUTEX: Mutex<ContextID> = Mutex::new(ContextID(0));
+}
+
+/// A unique identifier among all currently-live contexts.
+///
+/// If a context is destroyed, a subsequently-created context may reuse its ID.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ContextID(pub u64);
+
+/// Attributes that control how an OpenGL context and its default framebuffer are created.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ContextAttributes {
+    /// The OpenGL version that the context must support.
+    pub version: GLVersion,
+    /// Flags that select optional framebuffer channels and context behavior.
+    pub flags: ContextAttributeFlags,
+}
+
+bitflags! {
+    /// Flags that select optional OpenGL features when creating a context.
+    pub struct ContextAttributeFlags: u8 {
+        /// The default framebuffer should have an alpha channel.
+        const ALPHA      = 0x01;
+        /// The default framebuffer should have a depth buffer.
+        const DEPTH      = 0x02;
+        /// The default framebuffer should have a stencil buffer.
+        const STENCIL    = 0x04;
+        /// The context should be robust and survive GPU resets rather than aborting the process.
+        const ROBUSTNESS = 0x08;
+    }
+}
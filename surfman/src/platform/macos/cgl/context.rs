@@ -12,15 +12,20 @@ use super::error::ToWindowingApiError;
 use super::surface::Surface;
 
 use cgl::{CGLChoosePixelFormat, CGLContextObj, CGLCreateContext, CGLDescribePixelFormat};
-use cgl::{CGLDestroyContext, CGLError, CGLGetCurrentContext, CGLGetPixelFormat};
+use cgl::{CGLDescribeRenderer, CGLDestroyContext, CGLDestroyRendererInfo, CGLDisable};
+use cgl::{CGLError, CGLGetCurrentContext, CGLGetPixelFormat, CGLQueryRendererInfo};
+use cgl::{CGLRendererInfoObj, CGLRendererProperty, CGLSetVirtualScreen};
 use cgl::{CGLPixelFormatAttribute, CGLPixelFormatObj, CGLReleasePixelFormat, CGLRetainPixelFormat};
-use cgl::{CGLSetCurrentContext, kCGLPFAAllowOfflineRenderers, kCGLPFAAlphaSize, kCGLPFADepthSize};
+use cgl::{CGLSetCurrentContext, CGLSetParameter};
+use cgl::{kCGLPFAAllowOfflineRenderers, kCGLPFAAlphaSize, kCGLPFADepthSize};
 use cgl::{kCGLPFAStencilSize, kCGLPFAOpenGLProfile};
 use core_foundation::base::TCFType;
 use core_foundation::bundle::CFBundleGetBundleWithIdentifier;
 use core_foundation::bundle::CFBundleGetFunctionPointerForName;
 use core_foundation::bundle::CFBundleRef;
 use core_foundation::string::CFString;
+use raw_window_handle::RawWindowHandle;
+use std::marker::PhantomData;
 use std::mem;
 use std::os::raw::c_void;
 use std::ptr;
@@ -41,6 +46,32 @@ const kCGLOGLPVersion_3_2_Core: CGLPixelFormatAttribute = 0x3200;
 #[allow(non_upper_case_globals)]
 const kCGLOGLPVersion_GL4_Core: CGLPixelFormatAttribute = 0x4100;
 
+// Abort the process if a function removed from the current renderer is called. We turn this off
+// when a robust context is requested so that a GPU reset surfaces as a recoverable error instead.
+#[allow(non_upper_case_globals)]
+const kCGLCECrashOnRemovedFunctions: cgl::CGLContextEnable = 316;
+
+// Controls whether the drawable backing the context is treated as opaque during compositing.
+#[allow(non_upper_case_globals)]
+const kCGLCPSurfaceOpacity: cgl::CGLContextParameter = 236;
+
+// The renderer ID of a renderer in a renderer-info object.
+#[allow(non_upper_case_globals)]
+const kCGLRPRendererID: CGLRendererProperty = 70;
+// Whether a renderer is hardware-accelerated.
+#[allow(non_upper_case_globals)]
+const kCGLRPAccelerated: CGLRendererProperty = 73;
+// The amount of video memory, in megabytes, a renderer has.
+#[allow(non_upper_case_globals)]
+const kCGLRPVideoMemoryMegabytes: CGLRendererProperty = 131;
+
+// The number of virtual screens (renderer slots) a pixel format exposes.
+#[allow(non_upper_case_globals)]
+const kCGLPFAVirtualScreenCount: CGLPixelFormatAttribute = 128;
+// The renderer ID backing a particular virtual screen of a pixel format.
+#[allow(non_upper_case_globals)]
+const kCGLPFARendererID: CGLPixelFormatAttribute = 70;
+
 static OPENGL_FRAMEWORK_IDENTIFIER: &'static str = "com.apple.opengl";
 
 thread_local! {
@@ -60,17 +91,60 @@ thread_local! {
     };
 }
 
+/// A marker type for a context that is known not to be current on any thread.
+///
+/// See [`Context`] for the typestate these markers drive.
+pub enum NotCurrent {}
+
+/// A marker type for a context that may be current on the calling thread.
+///
+/// See [`Context`] for the typestate these markers drive.
+pub enum PossiblyCurrent {}
+
 /// An OpenGL context on macOS.
-/// 
+///
 /// OpenGL contexts must be explicitly destroyed with `Device::destroy_context()`, or a panic
 /// occurs.
-/// 
+///
 /// Contexts are specific to the device that created them and cannot be used with any other device.
 /// They are also not thread-safe, just as devices are not.
-pub struct Context {
+///
+/// The `S` type parameter tracks, at compile time, whether the context may be current on the
+/// calling thread. `Device::make_context_current` consumes a [`NotCurrentContext`] and returns a
+/// [`PossiblyCurrentContext`]; rendering-oriented methods that require the context to be current
+/// are only callable on the latter. The default `S` is [`PossiblyCurrent`], so the bare `Context`
+/// alias keeps working while callers migrate to the typed form.
+pub struct Context<S = PossiblyCurrent> {
     pub(crate) native_context: Box<dyn NativeContext>,
     pub(crate) id: ContextID,
     framebuffer: Framebuffer<Surface>,
+    robustness: Robustness,
+    phantom: PhantomData<S>,
+}
+
+/// A context that is known not to be current on any thread.
+pub type NotCurrentContext = Context<NotCurrent>;
+
+/// A context that may be current on the calling thread.
+pub type PossiblyCurrentContext = Context<PossiblyCurrent>;
+
+impl<S> Context<S> {
+    // Reinterprets this context under a different currentness marker.
+    //
+    // This only flips the compile-time typestate; it performs no CGL calls. `ManuallyDrop` keeps
+    // the source context's `Drop` from running while its fields are moved into the new marker.
+    fn change_state<S2>(self) -> Context<S2> {
+        let this = mem::ManuallyDrop::new(self);
+        unsafe {
+            Context {
+                native_context: ptr::read(&this.native_context),
+                id: this.id,
+                framebuffer: ptr::read(&this.framebuffer),
+                robustness: this.robustness,
+                phantom: PhantomData,
+            }
+        }
+    }
 }
 
 pub(crate) trait NativeContext {
@@ -79,7 +153,7 @@ pub(crate) trait NativeContext {
     unsafe fn destroy(&mut self);
 }
 
-impl Drop for Context {
+impl<S> Drop for Context<S> {
     #[inline]
     fn drop(&mut self) {
         if !self.native_context.is_destroyed() && !thread::panicking() {
@@ -93,6 +167,37 @@ impl Drop for Context {
 /// This corresponds to a "pixel format" object in many APIs. These are thread-safe.
 pub struct ContextDescriptor {
     cgl_pixel_format: CGLPixelFormatObj,
+    robustness: Robustness,
+}
+
+/// The level of robustness that a context provides in the face of GPU resets.
+///
+/// On CGL a robust context no longer aborts the process when functions disappear from the current
+/// renderer (it turns off `kCGLCECrashOnRemovedFunctions`), so a long-running compositor can treat
+/// a driver reset as a recoverable condition rather than crashing. CGL does not expose the
+/// `GL_ARB_robustness` reset-notification query, so the reset-notification variants below are
+/// best-effort requests for that behavior, not a guarantee that resets will be reported.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Robustness {
+    /// No robustness is requested; the context behaves like a normal OpenGL context.
+    NoRobustness,
+    /// A robust context is requested, but no reset-notification guarantees are needed.
+    TryRobustNoResetNotification,
+    /// A robust context that is expected to lose its state on a GPU reset is requested. CGL cannot
+    /// report the reset, so recovery is best-effort, as noted on the enum.
+    TryRobustLoseContextOnReset,
+}
+
+/// Which GPU a context should render on when more than one is available.
+///
+/// On macs with automatic graphics switching this lets power-sensitive apps keep rendering on the
+/// integrated GPU, or deliberately move heavy work to the discrete one, at runtime.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GpuPreference {
+    /// Render on the low-power integrated GPU.
+    Integrated,
+    /// Render on the high-performance discrete GPU.
+    Discrete,
 }
 
 impl Drop for ContextDescriptor {
@@ -111,6 +216,7 @@ impl Clone for ContextDescriptor {
         unsafe {
             ContextDescriptor {
                 cgl_pixel_format: CGLRetainPixelFormat(self.cgl_pixel_format),
+                robustness: self.robustness,
             }
         }
     }
@@ -118,6 +224,24 @@ impl Clone for ContextDescriptor {
 
 unsafe impl Send for ContextDescriptor {}
 
+impl ContextDescriptor {
+    /// The robustness level that contexts created from this descriptor will request.
+    #[inline]
+    pub fn robustness(&self) -> Robustness {
+        self.robustness
+    }
+
+    /// Sets the robustness level that contexts created from this descriptor will request.
+    ///
+    /// `create_context_descriptor` defaults this to `TryRobustLoseContextOnReset` when the
+    /// `ROBUSTNESS` attribute flag is set and `NoRobustness` otherwise; use this to request a
+    /// specific level, such as `TryRobustNoResetNotification`, explicitly.
+    #[inline]
+    pub fn set_robustness(&mut self, robustness: Robustness) {
+        self.robustness = robustness;
+    }
+}
+
 impl Device {
     /// Creates an OpenGL context descriptor object from the given set of attributes.
     /// 
@@ -133,6 +257,16 @@ impl Device {
         };
 
         let flags = attributes.flags;
+
+        // Requesting a robust context turns off the default abort-on-missing-function behavior so
+        // that a GPU reset surfaces as a recoverable error instead of aborting the process. CGL
+        // cannot report reset notifications, so this is the strongest level it can honor.
+        let robustness = if flags.contains(ContextAttributeFlags::ROBUSTNESS) {
+            Robustness::TryRobustLoseContextOnReset
+        } else {
+            Robustness::NoRobustness
+        };
+
         let alpha_size   = if flags.contains(ContextAttributeFlags::ALPHA)   { 8  } else { 0 };
         let depth_size   = if flags.contains(ContextAttributeFlags::DEPTH)   { 24 } else { 0 };
         let stencil_size = if flags.contains(ContextAttributeFlags::STENCIL) { 8  } else { 0 };
@@ -165,7 +299,7 @@ impl Device {
                 return Err(Error::NoPixelFormatFound);
             }
 
-            Ok(ContextDescriptor { cgl_pixel_format })
+            Ok(ContextDescriptor { cgl_pixel_format, robustness })
         }
     }
 
@@ -188,6 +322,9 @@ impl Device {
             native_context: Box::new(UnsafeCGLContextRef::current()),
             id: *next_context_id,
             framebuffer: Framebuffer::External,
+            // The robustness of a context created outside `surfman` is unknown.
+            robustness: Robustness::NoRobustness,
+            phantom: PhantomData,
         };
         next_context_id.0 += 1;
 
@@ -199,13 +336,46 @@ impl Device {
         Ok((Device(device), context))
     }
 
+    /// Creates a window surface wrapping the view referred to by a `raw-window-handle`.
+    ///
+    /// Unlike `from_current_context`, whose render target is opaque to `surfman`, the surface
+    /// returned here is a first-class `surfman` surface that records the view supplied by `winit`
+    /// or any other windowing crate, so that `surfman` owns the window render target rather than
+    /// treating it as external.
+    ///
+    /// Note that the surface is not yet presentable: this path installs a backing layer on the view
+    /// but does not yet connect the context's CGL drawable to it, so `context_surface_info` and
+    /// `present` do not render to the window until that wiring lands.
+    ///
+    /// Only `RawWindowHandle::AppKit` is supported; other handle kinds yield
+    /// `Error::IncompatibleNativeWidget`.
+    pub fn create_surface_from_raw_window_handle<S>(&mut self,
+                                                    context: &Context<S>,
+                                                    raw_handle: RawWindowHandle)
+                                                    -> Result<Surface, Error> {
+        let ns_view = match raw_handle {
+            RawWindowHandle::AppKit(handle) => handle.ns_view,
+            _ => return Err(Error::IncompatibleNativeWidget),
+        };
+
+        unsafe { Surface::from_nsview(self, context, ns_view as *mut c_void) }
+    }
+
     /// Creates an OpenGL context from the given descriptor.
-    /// 
+    ///
     /// The context must be destroyed with `Device::destroy_context()` when it is no longer needed,
     /// or a panic will occur.
-    /// 
+    ///
     /// The context will be local to this device and cannot be used with any other.
-    pub fn create_context(&mut self, descriptor: &ContextDescriptor) -> Result<Context, Error> {
+    ///
+    /// If `share_with` is supplied, the new context shares its OpenGL object namespace—textures,
+    /// buffers, renderbuffers, and so on—with that context. This allows a common multithreaded
+    /// pattern in which one context uploads resources while another renders from them. The shared
+    /// context must have been created by this device.
+    pub fn create_context(&mut self,
+                          descriptor: &ContextDescriptor,
+                          share_with: Option<&Context>)
+                          -> Result<NotCurrentContext, Error> {
         // Take a lock so that we're only creating one context at a time. This serves two purposes:
         //
         // 1. CGLChoosePixelFormat fails, returning `kCGLBadConnection`, if multiple threads try to
@@ -213,11 +383,17 @@ impl Device {
         // 2. The first thread to create a context needs to load the GL function pointers.
         let mut next_context_id = CREATE_CONTEXT_MUTEX.lock().unwrap();
 
+        // Share OpenGL objects with the given context, if any.
+        let share_context = match share_with {
+            Some(share_with) => share_with.native_context.cgl_context(),
+            None => ptr::null_mut(),
+        };
+
         unsafe {
             // Create the CGL context.
             let mut cgl_context = ptr::null_mut();
             let err = CGLCreateContext(descriptor.cgl_pixel_format,
-                                       ptr::null_mut(),
+                                       share_context,
                                        &mut cgl_context);
             if err != kCGLNoError {
                 return Err(Error::ContextCreationFailed(err.to_windowing_api_error()));
@@ -231,11 +407,28 @@ impl Device {
                 return Err(Error::MakeCurrentFailed(err.to_windowing_api_error()));
             }
 
+            // Apply the requested robustness level. Turning off the crash-on-removed-functions
+            // behavior lets a GPU reset surface as a recoverable error rather than aborting. CGL
+            // exposes no finer control, so if it refuses, the requested level is unavailable.
+            if descriptor.robustness != Robustness::NoRobustness {
+                let err = CGLDisable(native_context.cgl_context(),
+                                     kCGLCECrashOnRemovedFunctions);
+                if err != kCGLNoError {
+                    return Err(Error::Unsupported);
+                }
+            }
+
+            // Leave no context current so that the returned context is honestly `NotCurrent`; the
+            // caller makes it current via `make_context_current` when it is ready to render.
+            CGLSetCurrentContext(ptr::null_mut());
+
             // Wrap and return the context.
             let context = Context {
                 native_context,
                 id: *next_context_id,
                 framebuffer: Framebuffer::None,
+                robustness: descriptor.robustness,
+                phantom: PhantomData,
             };
             next_context_id.0 += 1;
             Ok(context)
@@ -243,7 +436,9 @@ impl Device {
     }
 
     /// Destroys an OpenGL context.
-    pub fn destroy_context(&self, context: &mut Context) -> Result<(), Error> {
+    ///
+    /// This is callable regardless of whether the context is current.
+    pub fn destroy_context<S>(&self, context: &mut Context<S>) -> Result<(), Error> {
         if context.native_context.is_destroyed() {
             return Ok(());
         }
@@ -262,53 +457,70 @@ impl Device {
 
     /// Returns the descriptor that the context was created with.
     #[inline]
-    pub fn context_descriptor(&self, context: &Context) -> ContextDescriptor {
+    pub fn context_descriptor<S>(&self, context: &Context<S>) -> ContextDescriptor {
         unsafe {
             let mut cgl_pixel_format = CGLGetPixelFormat(context.native_context.cgl_context());
             cgl_pixel_format = CGLRetainPixelFormat(cgl_pixel_format);
-            ContextDescriptor { cgl_pixel_format }
+            // CGL cannot recover the robustness level from a live context, so report the level the
+            // context was created with, which we track on the context itself.
+            ContextDescriptor { cgl_pixel_format, robustness: context.robustness }
         }
     }
 
     /// Makes the context the current rendering context for this thread.
-    /// 
-    /// After calling this method, OpenGL rendering commands will render via this context to the
+    ///
+    /// This consumes a [`NotCurrentContext`] and, on success, hands back a
+    /// [`PossiblyCurrentContext`] on which rendering-oriented methods become callable. After
+    /// calling this method, OpenGL rendering commands will render via this context to the
     /// currently-bound surface.
-    pub fn make_context_current(&self, context: &Context) -> Result<(), Error> {
+    ///
+    /// On failure the context is returned unchanged alongside the error so the caller can retry.
+    pub fn make_context_current(&self, context: NotCurrentContext)
+                                -> Result<PossiblyCurrentContext, (Error, NotCurrentContext)> {
         unsafe {
             let err = CGLSetCurrentContext(context.native_context.cgl_context());
             if err != kCGLNoError {
-                return Err(Error::MakeCurrentFailed(err.to_windowing_api_error()));
+                return Err((Error::MakeCurrentFailed(err.to_windowing_api_error()), context));
             }
-            Ok(())
         }
+        Ok(context.change_state())
     }
 
     /// Makes this thread have no current rendering context.
-    /// 
-    /// You should not call OpenGL rendering commands after calling this method until another
-    /// context becomes current.
-    pub fn make_no_context_current(&self) -> Result<(), Error> {
+    ///
+    /// This consumes a [`PossiblyCurrentContext`] and hands back a [`NotCurrentContext`]. You
+    /// should not call OpenGL rendering commands after calling this method until another context
+    /// becomes current.
+    ///
+    /// On failure the context is returned unchanged alongside the error so the caller can retry.
+    pub fn make_no_context_current(&self, context: PossiblyCurrentContext)
+                                   -> Result<NotCurrentContext, (Error, PossiblyCurrentContext)> {
         unsafe {
             let err = CGLSetCurrentContext(ptr::null_mut());
             if err != kCGLNoError {
-                return Err(Error::MakeCurrentFailed(err.to_windowing_api_error()));
+                return Err((Error::MakeCurrentFailed(err.to_windowing_api_error()), context));
             }
-            Ok(())
         }
+        Ok(context.change_state())
     }
 
-    pub(crate) fn temporarily_make_context_current(&self, context: &Context)
-                                                   -> Result<CurrentContextGuard, Error> {
+    pub(crate) fn temporarily_make_context_current<S>(&self, context: &Context<S>)
+                                                     -> Result<CurrentContextGuard, Error> {
         let guard = CurrentContextGuard::new();
-        self.make_context_current(context)?;
+        unsafe {
+            let err = CGLSetCurrentContext(context.native_context.cgl_context());
+            if err != kCGLNoError {
+                return Err(Error::MakeCurrentFailed(err.to_windowing_api_error()));
+            }
+        }
         Ok(guard)
     }
 
     /// Attaches a surface to this context.
-    /// 
-    /// Once this context becomes current, OpenGL rendering commands will render to the surface.
-    pub fn bind_surface_to_context(&self, context: &mut Context, new_surface: Surface)
+    ///
+    /// Only callable on a [`PossiblyCurrentContext`]: OpenGL rendering commands will render to the
+    /// surface once this context is current, so binding is part of the current-context workflow.
+    pub fn bind_surface_to_context(&self, context: &mut PossiblyCurrentContext, new_surface: Surface)
                                    -> Result<(), Error> {
         match context.framebuffer {
             Framebuffer::External => return Err(Error::ExternalRenderTarget),
@@ -329,8 +541,8 @@ impl Device {
     /// Once you call this method, OpenGL rendering commands will fail until a new surface is
     /// attached. (You can still use OpenGL commands that don't render to the default framebuffer,
     /// though, as long as the context is current.)
-    pub fn unbind_surface_from_context(&self, context: &mut Context)
-                                       -> Result<Option<Surface>, Error> {
+    pub fn unbind_surface_from_context<S>(&self, context: &mut Context<S>)
+                                          -> Result<Option<Surface>, Error> {
         match context.framebuffer {
             Framebuffer::External => return Err(Error::ExternalRenderTarget),
             Framebuffer::None | Framebuffer::Surface(_) => {}
@@ -355,6 +567,150 @@ impl Device {
         }
     }
 
+    /// Sets whether the surface bound to this context is opaque.
+    ///
+    /// By default a drawable is opaque. Making it non-opaque allows the window server to composite
+    /// the surface over other content, which is useful for translucent windows and overlays.
+    ///
+    /// This fails with `Error::ExternalRenderTarget` if the context renders to an externally
+    /// managed target, and with `Error::NoSurfaceAttached` if no surface is currently bound.
+    pub fn set_context_surface_opacity<S>(&self, context: &mut Context<S>, opaque: bool)
+                                          -> Result<(), Error> {
+        match context.framebuffer {
+            Framebuffer::External => return Err(Error::ExternalRenderTarget),
+            Framebuffer::None => return Err(Error::NoSurfaceAttached),
+            Framebuffer::Surface(_) => {}
+        }
+
+        let _guard = self.temporarily_make_context_current(context)?;
+        unsafe {
+            let value = opaque as i32;
+            let err = CGLSetParameter(context.native_context.cgl_context(),
+                                      kCGLCPSurfaceOpacity,
+                                      &value);
+            if err != kCGLNoError {
+                return Err(Error::Unsupported);
+            }
+        }
+        Ok(())
+    }
+
+    /// Pins this context to the integrated or discrete GPU.
+    ///
+    /// This enumerates the renderers backing the context's pixel format and selects a matching
+    /// virtual screen with `CGLSetVirtualScreen`, so the choice of GPU is per-context rather than
+    /// fixed by which device was opened. The integrated GPU is identified as the accelerated
+    /// renderer with the least video memory, and the discrete GPU as the one with the most.
+    ///
+    /// Fails with `Error::NoAdapterFound` if no renderer matches the requested preference.
+    pub fn set_context_renderer<S>(&self, context: &mut Context<S>, preference: GpuPreference)
+                                   -> Result<(), Error> {
+        unsafe {
+            let cgl_pixel_format = CGLGetPixelFormat(context.native_context.cgl_context());
+
+            // How many virtual screens—i.e. renderer slots—the context's pixel format exposes.
+            let mut virtual_screen_count = 0;
+            let err = CGLDescribePixelFormat(cgl_pixel_format,
+                                             0,
+                                             kCGLPFAVirtualScreenCount,
+                                             &mut virtual_screen_count);
+            if err != kCGLNoError {
+                return Err(Error::NoAdapterFound);
+            }
+
+            // Enumerate the system renderers so we can look up acceleration and video memory by
+            // renderer ID.
+            let (mut renderer_info, mut renderer_count) = (ptr::null_mut(), 0);
+            let err = CGLQueryRendererInfo(0xffffffff, &mut renderer_info, &mut renderer_count);
+            if err != kCGLNoError {
+                return Err(Error::NoAdapterFound);
+            }
+
+            // Walk the pixel format's *own* virtual screens, match each to its backing renderer by
+            // renderer ID, and pick the accelerated one with the least (integrated) or most
+            // (discrete) video memory. The chosen index is therefore a valid virtual screen for
+            // `CGLSetVirtualScreen`, not an unrelated global renderer index.
+            let mut best: Option<(i32, i32)> = None;
+            for virtual_screen in 0..virtual_screen_count {
+                let mut renderer_id = 0;
+                let err = CGLDescribePixelFormat(cgl_pixel_format,
+                                                 virtual_screen,
+                                                 kCGLPFARendererID,
+                                                 &mut renderer_id);
+                if err != kCGLNoError {
+                    continue;
+                }
+
+                let renderer_index = match renderer_index_for_id(renderer_info,
+                                                                 renderer_count,
+                                                                 renderer_id) {
+                    Some(renderer_index) => renderer_index,
+                    None => continue,
+                };
+
+                if describe_renderer(renderer_info, renderer_index, kCGLRPAccelerated) == 0 {
+                    continue;
+                }
+
+                let memory = describe_renderer(renderer_info,
+                                               renderer_index,
+                                               kCGLRPVideoMemoryMegabytes);
+                let better = match best {
+                    None => true,
+                    Some((_, best_memory)) => match preference {
+                        GpuPreference::Integrated => memory < best_memory,
+                        GpuPreference::Discrete => memory > best_memory,
+                    },
+                };
+                if better {
+                    best = Some((virtual_screen, memory));
+                }
+            }
+
+            CGLDestroyRendererInfo(renderer_info);
+
+            let virtual_screen = match best {
+                Some((virtual_screen, _)) => virtual_screen,
+                None => return Err(Error::NoAdapterFound),
+            };
+
+            let err = CGLSetVirtualScreen(context.native_context.cgl_context(), virtual_screen);
+            if err != kCGLNoError {
+                return Err(Error::NoAdapterFound);
+            }
+        }
+
+        return Ok(());
+
+        unsafe fn describe_renderer(renderer_info: CGLRendererInfoObj,
+                                    index: i32,
+                                    property: CGLRendererProperty)
+                                    -> i32 {
+            let mut value = 0;
+            let err = CGLDescribeRenderer(renderer_info, index, property, &mut value);
+            debug_assert_eq!(err, kCGLNoError);
+            value
+        }
+
+        // Finds the renderer-info index whose renderer ID matches a pixel format's virtual-screen
+        // renderer ID, comparing only the bits CGL uses to identify a renderer.
+        unsafe fn renderer_index_for_id(renderer_info: CGLRendererInfoObj,
+                                        renderer_count: i32,
+                                        renderer_id: i32)
+                                        -> Option<i32> {
+            // `kCGLRendererIDMatchingMask`.
+            const RENDERER_ID_MATCHING_MASK: i32 = 0x00fe_7f00u32 as i32;
+            for index in 0..renderer_count {
+                let this_id = describe_renderer(renderer_info, index, kCGLRPRendererID);
+                if (this_id & RENDERER_ID_MATCHING_MASK) ==
+                        (renderer_id & RENDERER_ID_MATCHING_MASK) {
+                    return Some(index);
+                }
+            }
+            None
+        }
+    }
+
     /// Returns the attributes that the given context descriptor represents.
     pub fn context_descriptor_attributes(&self, context_descriptor: &ContextDescriptor)
                                          -> ContextAttributes {
@@ -393,12 +749,13 @@ impl Device {
     /// The symbol name should include the `gl` prefix, if any. OpenGL symbols are local to a
     /// context and should be reloaded if switching contexts.
     #[inline]
-    pub fn get_proc_address(&self, _: &Context, symbol_name: &str) -> *const c_void {
+    pub fn get_proc_address(&self, _: &PossiblyCurrentContext, symbol_name: &str) -> *const c_void {
         get_proc_address(symbol_name)
     }
 
     /// Retrieves various information about the surface currently attached to this context, if any.
-    pub fn context_surface_info(&self, context: &Context) -> Result<Option<SurfaceInfo>, Error> {
+    pub fn context_surface_info(&self, context: &PossiblyCurrentContext)
+                                -> Result<Option<SurfaceInfo>, Error> {
         match context.framebuffer {
             Framebuffer::None => Ok(None),
             Framebuffer::External => Err(Error::ExternalRenderTarget),
@@ -411,7 +768,7 @@ impl Device {
     /// Context IDs are unique to all currently-live contexts. If a context is destroyed, then
     /// subsequently-created contexts may reuse the same ID.
     #[inline]
-    pub fn context_id(&self, context: &Context) -> ContextID {
+    pub fn context_id<S>(&self, context: &Context<S>) -> ContextID {
         context.id
     }
 }
@@ -498,4 +855,96 @@ impl CurrentContextGuard {
             CurrentContextGuard { old_cgl_context: CGLGetCurrentContext() }
         }
     }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::platform::macos::system::connection::Connection as SystemConnection;
+    use crate::{ContextAttributeFlags, ContextAttributes, Error, GLVersion};
+    use super::{Device, GpuPreference, Robustness};
+
+    use raw_window_handle::{RawWindowHandle, Win32Handle};
+
+    fn create_device() -> Device {
+        let connection = SystemConnection::new().unwrap();
+        let adapter = connection.create_adapter().unwrap();
+        Device(connection.create_device(&adapter).unwrap())
+    }
+
+    fn attributes() -> ContextAttributes {
+        ContextAttributes { version: GLVersion::new(3, 2), flags: ContextAttributeFlags::empty() }
+    }
+
+    #[test]
+    fn descriptor_enums_are_comparable_values() {
+        // The descriptor enums are plain `Copy` value types, so callers can store and compare the
+        // GPU and robustness choices they requested.
+        assert_eq!(Robustness::TryRobustLoseContextOnReset, Robustness::TryRobustLoseContextOnReset);
+        assert_ne!(Robustness::NoRobustness, Robustness::TryRobustNoResetNotification);
+        assert_ne!(GpuPreference::Integrated, GpuPreference::Discrete);
+    }
+
+    #[test]
+    fn descriptor_attributes_round_trip() {
+        let device = create_device();
+        let mut attributes = attributes();
+        attributes.flags.insert(ContextAttributeFlags::ALPHA | ContextAttributeFlags::DEPTH);
+
+        let descriptor = device.create_context_descriptor(&attributes).unwrap();
+        let recovered = device.context_descriptor_attributes(&descriptor);
+
+        assert!(recovered.flags.contains(ContextAttributeFlags::ALPHA));
+        assert!(recovered.flags.contains(ContextAttributeFlags::DEPTH));
+        assert!(!recovered.flags.contains(ContextAttributeFlags::STENCIL));
+    }
+
+    #[test]
+    fn descriptor_robustness_is_selectable_and_round_trips() {
+        let mut device = create_device();
+
+        // The `ROBUSTNESS` flag yields the strongest level CGL can honor.
+        let mut flagged = attributes();
+        flagged.flags.insert(ContextAttributeFlags::ROBUSTNESS);
+        let descriptor = device.create_context_descriptor(&flagged).unwrap();
+        assert_eq!(descriptor.robustness(), Robustness::TryRobustLoseContextOnReset);
+
+        // Callers can select a specific level explicitly, and it survives onto the context.
+        let mut descriptor = device.create_context_descriptor(&attributes()).unwrap();
+        assert_eq!(descriptor.robustness(), Robustness::NoRobustness);
+        descriptor.set_robustness(Robustness::TryRobustNoResetNotification);
+
+        let mut context = device.create_context(&descriptor, None).unwrap();
+        assert_eq!(device.context_descriptor(&context).robustness(),
+                   Robustness::TryRobustNoResetNotification);
+        device.destroy_context(&mut context).unwrap();
+    }
+
+    #[test]
+    fn opacity_without_surface_is_rejected() {
+        let mut device = create_device();
+        let descriptor = device.create_context_descriptor(&attributes()).unwrap();
+        let mut context = device.create_context(&descriptor, None).unwrap();
+
+        match device.set_context_surface_opacity(&mut context, false) {
+            Err(Error::NoSurfaceAttached) => {}
+            other => panic!("expected `NoSurfaceAttached`, got {:?}", other),
+        }
+
+        device.destroy_context(&mut context).unwrap();
+    }
+
+    #[test]
+    fn raw_window_handle_rejects_non_appkit() {
+        let mut device = create_device();
+        let descriptor = device.create_context_descriptor(&attributes()).unwrap();
+        let mut context = device.create_context(&descriptor, None).unwrap();
+
+        let handle = RawWindowHandle::Win32(Win32Handle::empty());
+        match device.create_surface_from_raw_window_handle(&context, handle) {
+            Err(Error::IncompatibleNativeWidget) => {}
+            other => panic!("expected `IncompatibleNativeWidget`, got {:?}", other),
+        }
+
+        device.destroy_context(&mut context).unwrap();
+    }
 }
\ No newline at end of file
@@ -0,0 +1,100 @@
+// surfman/surfman/src/platform/macos/cgl/surface.rs
+//
+//! CGL surfaces.
+//!
+//! A surface is either an off-screen `IOSurface`-backed render target or an on-screen window built
+//! around a `raw-window-handle` `NSView`. Off-screen surfaces are created with
+//! `Device::create_surface`; on-screen surfaces are created from a `raw-window-handle` via
+//! `Device::create_surface_from_raw_window_handle`, which calls [`Surface::from_nsview`]. The
+//! on-screen drawable wiring (`CAOpenGLLayer`/`CGLSetSurface`) is not yet in place, so window
+//! surfaces are not presentable yet.
+
+use crate::context::ContextID;
+use crate::Error;
+use super::context::{Context, Device};
+
+use cocoa::base::{id, nil, YES};
+use cocoa::quartzcore::{CALayer, transaction};
+use core_foundation::base::TCFType;
+use euclid::default::Size2D;
+use objc::{msg_send, sel, sel_impl};
+use std::os::raw::c_void;
+
+/// A native widget: the `NSView` whose layer backs an on-screen surface.
+///
+/// `surfman` does not retain the view; the caller must keep it alive for as long as the surface
+/// that wraps it.
+pub struct NativeWidget {
+    pub(crate) ns_view: id,
+}
+
+/// A rectangular region that OpenGL can render to.
+pub struct Surface {
+    pub(crate) context_id: ContextID,
+    pub(crate) size: Size2D<i32>,
+    pub(crate) objects: SurfaceObjects,
+    pub(crate) destroyed: bool,
+}
+
+pub(crate) enum SurfaceObjects {
+    /// An off-screen surface backed by an `IOSurface`.
+    ///
+    /// This variant carries no fields; the `IOSurface` objects are not modeled in this module.
+    Offscreen,
+    /// An on-screen surface built around the `CALayer` installed on an `NSView`. Nothing is
+    /// rendered into the layer yet.
+    Window {
+        native_widget: NativeWidget,
+        layer: CALayer,
+    },
+}
+
+unsafe impl Send for Surface {}
+
+impl Surface {
+    /// Wraps the view referred to by a `raw-window-handle` `NSView` in an on-screen surface.
+    ///
+    /// The view is made layer-backed with a `CALayer` sized to its bounds, and the surface records
+    /// the view and layer, the size of the view's bounds, and the ID of `context`; it does not
+    /// retain the view.
+    ///
+    /// This does not yet connect the context's CGL drawable to the layer (there is no
+    /// `CAOpenGLLayer`/`CGLSetSurface` wiring), so the resulting surface is not presentable: it
+    /// only captures the window target until that drawable wiring lands.
+    pub(crate) unsafe fn from_nsview<S>(device: &mut Device,
+                                        context: &Context<S>,
+                                        ns_view: *mut c_void)
+                                        -> Result<Surface, Error> {
+        let ns_view = ns_view as id;
+        if ns_view == nil {
+            return Err(Error::IncompatibleNativeWidget);
+        }
+
+        // Make the view layer-backed so that, once the drawable wiring lands, the window server
+        // can composite what the context draws. Wrap the mutation in a transaction with implicit
+        // animations disabled, as Apple requires when touching layers off the main run loop.
+        transaction::begin();
+        transaction::set_disable_actions(true);
+
+        let layer = CALayer::new();
+        let bounds: core_graphics::geometry::CGRect = msg_send![ns_view, bounds];
+        let _: () = msg_send![ns_view, setWantsLayer: YES];
+        let _: () = msg_send![ns_view, setLayer: layer.id()];
+        layer.set_frame(&bounds);
+
+        transaction::commit();
+
+        let size = Size2D::new(bounds.size.width as i32, bounds.size.height as i32);
+        let _ = device;
+
+        Ok(Surface {
+            context_id: context.id,
+            size,
+            objects: SurfaceObjects::Window {
+                native_widget: NativeWidget { ns_view },
+                layer,
+            },
+            destroyed: false,
+        })
+    }
+}